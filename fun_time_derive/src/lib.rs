@@ -1,5 +1,5 @@
 use darling::FromMeta;
-use quote::quote;
+use quote::{format_ident, quote};
 use syn::{parse_macro_input, ReturnType};
 
 macro_rules! make_darling_error {
@@ -10,7 +10,7 @@ macro_rules! make_darling_error {
 
 macro_rules! make_compile_error {
     ($($arg:tt)*) => {
-        quote! { compile_error!($($arg)*) }.into()
+        quote! { compile_error!($($arg)*); }.into()
     };
 }
 
@@ -58,6 +58,16 @@ enum Reporting {
     /// provided `info!` macro.
     #[cfg(feature = "log")]
     Log,
+    /// Use the [puffin](https://crates.io/crates/puffin) crate to profile the function in a scope
+    /// named after the function, with the message as scope data. No start and done messages are
+    /// printed.
+    #[cfg(feature = "puffin")]
+    Puffin,
+    /// Use the [minitrace](https://crates.io/crates/minitrace) crate to trace the function in a
+    /// local span named after the function, with the message as `message` property and every
+    /// parameter as a property of its own. No start and done messages are printed.
+    #[cfg(feature = "minitrace")]
+    Minitrace,
 }
 
 /// By default we use the simple `println!` to write the reporting info to the `stdout`.
@@ -80,7 +90,11 @@ impl Reporting {
             "println" => Ok(Self::Println),
             #[cfg(feature = "log")]
             "log" => Ok(Self::Log),
-            unsupported => make_darling_error!("Unsupported value for `reporting` attribute: {unsupported}. Use one of: println, (only with log feature) log")
+            #[cfg(feature = "puffin")]
+            "puffin" => Ok(Self::Puffin),
+            #[cfg(feature = "minitrace")]
+            "minitrace" => Ok(Self::Minitrace),
+            unsupported => make_darling_error!("Unsupported value for `reporting` attribute: {unsupported}. Use one of: println, (only with log feature) log, (only with puffin feature) puffin, (only with minitrace feature) minitrace")
         }
     }
 }
@@ -116,6 +130,10 @@ mod log_level {
 struct FunTimeArgs {
     #[darling(default)]
     message: Option<String>,
+    /// Message to use for the done message instead of `message` when the function body signalled
+    /// an early return using `fun_time::early!()`.
+    #[darling(default)]
+    early_return_message: Option<String>,
     /// Determines when we should perform the timing.
     #[darling(default)]
     #[darling(and_then = "When::from_lit")]
@@ -127,6 +145,48 @@ struct FunTimeArgs {
     #[darling(default)]
     #[darling(and_then = "Reporting::from_lit")]
     reporting: Reporting,
+    /// Determines whether the number of decimals of the elapsed time depends on its magnitude.
+    #[darling(default)]
+    adaptive_precision: bool,
+    /// Determines whether panics while reporting are caught.
+    #[darling(default)]
+    safe_reporting: bool,
+    /// Determines whether the first call of the function is marked as cold.
+    #[darling(default)]
+    mark_cold: bool,
+    /// Determines whether the elapsed time is compared to the running mean of the function.
+    #[darling(default)]
+    relative: bool,
+    /// Determines whether the `println` reporting uses the `stderr` in tests.
+    #[darling(default)]
+    respect_test_capture: bool,
+    /// Determines whether the elapsed time is compared to the previous call of the function.
+    #[darling(default)]
+    delta: bool,
+    /// Determines whether a microsecond Unix timestamp is added to the done message.
+    #[darling(default)]
+    epoch_us: bool,
+    /// Determines whether the elapsed time should be accumulated in the global registry.
+    #[darling(default)]
+    accumulate: bool,
+    /// The budget for the mean elapsed time, like `"10ms"`, implies `accumulate`.
+    #[darling(default)]
+    static_budget: Option<String>,
+    /// Determines whether the function is reported and recorded by its path of timed functions.
+    #[darling(default)]
+    hierarchical: bool,
+    /// Determines whether the last elapsed time is kept, to query it by name.
+    #[darling(default)]
+    last_elapsed: bool,
+    /// The number of first calls that are not accumulated.
+    #[darling(default)]
+    warmup_calls: Option<u64>,
+    /// Determines whether the generated code should also be made available as a string constant.
+    #[darling(default)]
+    expand_debug: bool,
+    /// Comma separated parameters that are not added as properties with the `minitrace` reporting.
+    #[darling(default)]
+    skip_properties: Option<String>,
 
     #[cfg(feature = "log")]
     #[darling(default)]
@@ -163,6 +223,14 @@ struct FunTimeArgs {
 /// chose to let the macro report the elapsed time directly. This message will be shown both in
 /// the start and done messages.
 ///
+/// ## early_return_message
+///
+/// The `early_return_message` attribute allows you to set a different message that will be
+/// displayed in the done message when the function returned early. Because detecting an early
+/// return is not possible in general, the function has to mark its early returns by calling
+/// `fun_time::early!()` right before the `return` statement. Like `message`, this message is
+/// passed to the `format!` macro.
+///
 /// ## reporting
 ///
 /// The `reporting` attribute determines how the message and elapsed time will be displayed
@@ -170,6 +238,136 @@ struct FunTimeArgs {
 /// it uses a simple `println!` statement, but with the optional `log` feature it will use the
 /// [log](https://crates.io/crates/log) crate to log it using the `info!` macro.
 ///
+/// With the optional `puffin` feature, `"puffin"` can be used to profile the function with
+/// [puffin](https://crates.io/crates/puffin) instead, for example to view it in `puffin_egui`. The
+/// function body is put in a `puffin::profile_scope!` named after the function, with the message
+/// as scope data. The message is only formatted when puffin profiling is turned on, so this costs
+/// next to nothing when it is turned off.
+///
+/// With the optional `minitrace` feature, `"minitrace"` can be used to trace the function with
+/// [minitrace](https://crates.io/crates/minitrace) instead. The function body is put in a local
+/// span named after the function, with the message, if any, as `message` property. Every parameter
+/// that is bound to a plain identifier is added as a property named after it, using its `Debug`
+/// output, so those parameters must implement `Debug`. Parameters starting with an underscore are
+/// left out, see `skip_properties` to leave out others. The properties are only formatted when
+/// the span is recorded. When there is no parent span, for example because no collector is set up,
+/// minitrace does not record anything.
+///
+/// Neither `"puffin"` nor `"minitrace"` prints any messages, so the attributes that only change the
+/// printed messages, like `relative` or `delta`, can not be used together with them.
+///
+/// ## skip_properties
+///
+/// The `skip_properties` attribute can be used together with `reporting = "minitrace"` to leave
+/// parameters out of the properties of the span, for example
+/// `skip_properties = "connection, callback"` for parameters that do not implement `Debug` or
+/// that are too large to record.
+///
+/// ## adaptive_precision
+///
+/// The `adaptive_precision` attribute can be used to pick the number of decimals of the reported
+/// elapsed time based on its magnitude, instead of always using 2 decimals. Nanoseconds are shown
+/// without decimals, microseconds with 3, milliseconds with 2 and seconds with 1 decimal.
+///
+/// ## safe_reporting
+///
+/// The `safe_reporting` attribute can be used to make sure the reporting can never crash the
+/// function, for example when the `Debug` implementation of an argument used in the `message`
+/// panics. The formatting and reporting is wrapped in [`std::panic::catch_unwind`], and when it
+/// panics a fallback message is printed to the `stderr` and the function continues as usual. If
+/// formatting the message panicked, the name of the function is used as the message instead.
+///
+/// ## mark_cold
+///
+/// The `mark_cold` attribute can be used to append `[cold]` to the done message of the first call
+/// of the function, to help identify cold-start costs like lazy initialization. After the first
+/// call, checking for it only costs a relaxed atomic load.
+///
+/// ## relative
+///
+/// The `relative` attribute can be used to append the ratio between the elapsed time and the
+/// running mean of the function to the done message, for example `(1.8x mean)`, to show whether
+/// a call was slower or faster than usual. The running mean is an exponential moving average in
+/// which every call has a weight of 10%, so it takes a few calls to settle. The first call has no
+/// mean to compare to yet and shows `(n/a mean)`.
+///
+/// ## respect_test_capture
+///
+/// The `respect_test_capture` attribute can be used to keep the messages of the `"println"`
+/// reporting apart from the output of your tests. When the function is compiled with `cfg(test)`,
+/// the messages are printed using `eprintln!` instead of `println!`. The test harness captures
+/// both, so they are only shown for failed tests, or with `--show-output`, but when running with
+/// `--nocapture` the `stdout` of your tests is no longer interleaved with timing messages. It can
+/// only be used together with `reporting = "println"`, so set it explicitly when the `log` feature
+/// makes `"log"` the default.
+///
+/// ## delta
+///
+/// The `delta` attribute can be used to append the difference with the elapsed time of the
+/// previous call of the function to the done message, for example `(Δ +3.00ms)` when this call
+/// was 3 milliseconds slower. This helps to watch the trend while tuning. The first call has
+/// nothing to compare to, and shows no delta.
+///
+/// ## epoch_us
+///
+/// The `epoch_us` attribute can be used to append the time at which the function was done to the
+/// done message, as `epoch_us=1700000000000000`, the number of microseconds since the Unix epoch.
+/// This gives a precise and sortable timestamp to correlate the message with other logs. If the
+/// system clock is set before the Unix epoch, the number is negative.
+///
+/// ## accumulate
+///
+/// The `accumulate` attribute can be used to record the elapsed time of every call in a global
+/// registry under the name of the function, which can be used in both the printing and the
+/// `give_back` mode. Use `fun_time::report_totals()`, for example when your program exits, to
+/// report the number of calls, the total, mean, min and max elapsed time, the
+/// `fun_time::SLOWEST_CALLS_KEPT` slowest calls and the observed calls per second of every
+/// function.
+///
+/// While a CSV log is open, see `fun_time::open_log()`, every recorded call is appended to it as
+/// well. Calls of functions without `accumulate` or `static_budget` are not recorded, so they do
+/// not end up in the log.
+///
+/// ## static_budget
+///
+/// The `static_budget` attribute can be used to set a budget for the mean elapsed time of the
+/// function, for example `"10ms"`. Supported units are `ns`, `us` (or `µs`), `ms` and `s`. It
+/// records every call like `accumulate` does, and makes `fun_time::report_totals()` return the
+/// functions of which the mean elapsed time exceeds their budget as an error, so you can use it
+/// to fail a test in CI. The budget can not be checked at compile time, it is checked when the
+/// totals are reported.
+///
+/// ## warmup_calls
+///
+/// The `warmup_calls` attribute can be used together with `accumulate` or `static_budget` to skip
+/// accumulating the first calls of the function, for example `warmup_calls = 5`, so cold-start
+/// effects do not skew the totals. The calls are still reported as usual.
+///
+/// ## hierarchical
+///
+/// The `hierarchical` attribute can be used to report and accumulate the function under its path
+/// in the call hierarchy, like `outer/inner/leaf`, instead of under its own name. The path is made
+/// up of the enclosing calls of functions that use this attribute on the same thread, so other
+/// functions in between are skipped. The messages are prefixed with the path, and the
+/// accumulated times are recorded under it, so the totals reflect the call tree.
+///
+/// ## last_elapsed
+///
+/// The `last_elapsed` attribute can be used to keep the elapsed time of the last call of the
+/// function, which can then be queried with `fun_time::last_elapsed("function_name")` without
+/// changing the return type like `give_back` does. The function is also documented to be timed.
+/// The elapsed time is kept by the name of the function, so functions that share a name, like
+/// methods in different impl blocks, should not both use this attribute.
+///
+/// ## expand_debug
+///
+/// The `expand_debug` attribute can be used to inspect the code generated by this attribute
+/// without the need for `cargo expand`. Next to the function, it generates a string constant
+/// containing the pretty-printed generated code, named after the function in uppercase with a
+/// `_FUN_TIME_EXPANSION` suffix. For methods this is an associated constant, which means it can
+/// not be used in trait implementations. Combined with `when = "debug"`, the constant contains the
+/// untouched function when it is not timed.
+///
 /// # Example
 ///
 /// ```
@@ -206,22 +404,150 @@ pub fn fun_time(
         );
     }
 
+    // Other attributes that only affect the reporting can not be combined with `give_back` either
+    let reporting_only_attributes = [
+        ("early_return_message", args.early_return_message.is_some()),
+        ("adaptive_precision", args.adaptive_precision),
+        ("safe_reporting", args.safe_reporting),
+        ("mark_cold", args.mark_cold),
+        ("relative", args.relative),
+        ("respect_test_capture", args.respect_test_capture),
+        ("epoch_us", args.epoch_us),
+        ("delta", args.delta),
+    ];
+    if let Some((attribute, _)) = reporting_only_attributes
+        .iter()
+        .find(|(_, used)| *used && args.give_back)
+    {
+        let error =
+            format!("the `{attribute}` and `give_back` attributes can not be used together!");
+        return make_compile_error!(#error);
+    }
+
+    // Only the `println!` reporting can switch to `eprintln!`
+    if args.respect_test_capture && !matches!(args.reporting, Reporting::Println) {
+        return make_compile_error!(
+            "the `respect_test_capture` attribute can only be used together with `reporting = \"println\"`!"
+        );
+    }
+
+    // The profiling backends do not print any messages, so attributes that only shape the printed
+    // messages would silently do nothing
+    let profiler: Option<&str> = match args.reporting {
+        #[cfg(feature = "puffin")]
+        Reporting::Puffin => Some("puffin"),
+        #[cfg(feature = "minitrace")]
+        Reporting::Minitrace => Some("minitrace"),
+        _ => None,
+    };
+    let printed_message_attributes = [
+        ("early_return_message", args.early_return_message.is_some()),
+        ("adaptive_precision", args.adaptive_precision),
+        ("mark_cold", args.mark_cold),
+        ("relative", args.relative),
+        ("epoch_us", args.epoch_us),
+        ("delta", args.delta),
+    ];
+    if let (Some(profiler), Some((attribute, _))) = (
+        profiler,
+        printed_message_attributes.iter().find(|(_, used)| *used),
+    ) {
+        let error = format!(
+            "the `{attribute}` attribute can not be used together with `reporting = \"{profiler}\"`!"
+        );
+        return make_compile_error!(#error);
+    }
+
+    if args.skip_properties.is_some() && profiler != Some("minitrace") {
+        return make_compile_error!(
+            "the `skip_properties` attribute can only be used together with `reporting = \"minitrace\"`!"
+        );
+    }
+
+    if args.warmup_calls.is_some() && !args.accumulate && args.static_budget.is_none() {
+        return make_compile_error!(
+            "the `warmup_calls` attribute requires the `accumulate` or `static_budget` attribute!"
+        );
+    }
+
+    // Parsed before the `when` check, so the budget is validated in every profile
+    let budget = match args.static_budget.as_deref().map(parse_budget).transpose() {
+        Ok(budget) => budget,
+        Err(error) => return make_compile_error!(#error),
+    };
+
     let item_fn: syn::ItemFn = parse_macro_input!(item as syn::ItemFn);
 
     // Check if we should time the function
     match args.when {
         When::Debug if args.give_back => return make_compile_error!("the `give_back` and `when` attribute with `\"debug\"` can not be used together! It would result in different return types"),
-        When::Debug if !cfg!(debug_assertions) => return quote! { #item_fn }.into(),
+        When::Debug if !cfg!(debug_assertions) => {
+            // The constant is still generated, so code using it compiles in both profiles
+            let tokens = quote! { #item_fn };
+            return if args.expand_debug {
+                with_expansion(tokens, &item_fn.vis, &item_fn.sig.ident).into()
+            } else {
+                tokens.into()
+            };
+        }
         _ => {} // No restrictions, go ahead!
     }
 
     let visibility = item_fn.vis;
+    let fn_ident = item_fn.sig.ident.clone();
     let signature = item_fn.sig.clone();
     let output = item_fn.sig.output;
 
     // Contains the original logic of the function
     let block = item_fn.block;
 
+    // In hierarchical mode the function is known by its path of timed functions, like
+    // `outer/inner/leaf`, instead of by its name.
+    let (hierarchy_statement, record_name) = if args.hierarchical {
+        let name = fn_ident.to_string();
+        (
+            quote! {
+                let super_secret_variable_that_does_not_clash_hierarchy = ::fun_time::__private::HierarchyGuard::enter(#name);
+            },
+            quote! { super_secret_variable_that_does_not_clash_hierarchy.path() },
+        )
+    } else {
+        let name = fn_ident.to_string();
+        (quote! {}, quote! { #name })
+    };
+
+    let accumulate_statement = match budget {
+        Some(budget_nanos) => quote! {
+            ::fun_time::record_with_budget(#record_name, elapsed, std::time::Duration::from_nanos(#budget_nanos));
+        },
+        None if args.accumulate => quote! { ::fun_time::record(#record_name, elapsed); },
+        None => quote! {},
+    };
+
+    // Skip accumulating the first calls, counting them in a static of the function
+    let accumulate_statement = match args.warmup_calls {
+        Some(warmup_calls) => quote! {
+            static SUPER_SECRET_VARIABLE_THAT_DOES_NOT_CLASH_CALLS: std::sync::atomic::AtomicU64 =
+                std::sync::atomic::AtomicU64::new(0);
+            if ::fun_time::__private::is_warmed_up(&SUPER_SECRET_VARIABLE_THAT_DOES_NOT_CLASH_CALLS, #warmup_calls) {
+                #accumulate_statement
+            }
+        },
+        None => accumulate_statement,
+    };
+
+    // Keep the last elapsed time in a static of the function, so it can be queried by name
+    let last_elapsed_statement = if args.last_elapsed {
+        let name = fn_ident.to_string();
+        quote! {
+            static SUPER_SECRET_VARIABLE_THAT_DOES_NOT_CLASH_LAST_ELAPSED: ::fun_time::__private::LastElapsed =
+                ::fun_time::__private::LastElapsed::new(#name);
+            SUPER_SECRET_VARIABLE_THAT_DOES_NOT_CLASH_LAST_ELAPSED.store(elapsed);
+        }
+    } else {
+        quote! {}
+    };
+
     // Create wrapped function block
     let wrapped_block = quote! {
         let super_secret_variable_that_does_not_clash_start = std::time::Instant::now();
@@ -233,6 +559,9 @@ pub fn fun_time(
         let return_value = (|| #output #block)();
 
         let elapsed = super_secret_variable_that_does_not_clash_start.elapsed();
+
+        #accumulate_statement
+        #last_elapsed_statement
     };
 
     // Create tokens for the `log` call if it is enabled
@@ -259,56 +588,277 @@ pub fn fun_time(
 
         // Modify our output type to also return a std::time::Duration (our elapsed time)
         // In case of an empty return type we can simply return the std::time::Duration, otherwise
-        // we have to wrap it in a tuple.
-        let output_with_duration = match output {
-            ReturnType::Default => syn::parse_str::<ReturnType>("-> std::time::Duration").unwrap(),
-            ReturnType::Type(_, ty) => syn::parse_str::<ReturnType>(&format!(
-                "-> ({}, std::time::Duration)",
-                quote! { #ty }
-            ))
-                .unwrap(),
+        // we have to wrap it in a tuple. The original type is kept as tokens instead of going
+        // through a string, so paths like `<Self as Trait>::Output` keep their spans.
+        let output_with_duration: ReturnType = match output {
+            ReturnType::Default => syn::parse_quote! { -> std::time::Duration },
+            ReturnType::Type(_, ty) => syn::parse_quote! { -> (#ty, std::time::Duration) },
         };
 
         quote! {
             #visibility fn #ident #generics (#inputs) #output_with_duration #where_clause {
+                #hierarchy_statement
                 #wrapped_block
 
                 (return_value, elapsed)
             }
         }
     } else {
+        #[cfg(feature = "minitrace")]
+        let has_message = args.message.is_some();
         let message = args.message.unwrap_or_default();
 
+        // With safe reporting a panic while formatting or reporting, for example in the `Debug`
+        // implementation of an argument, is caught so it can not take down the function.
+        let name = fn_ident.to_string();
+        let safe_reporting = args.safe_reporting;
+        let hierarchical = args.hierarchical;
+        let format_message = |message: &str| {
+            let formatted = if safe_reporting {
+                quote! {
+                    ::fun_time::__private::report_safely(#name, || format!(#message))
+                        .unwrap_or_else(|| String::from(#name))
+                }
+            } else {
+                quote! { format!(#message) }
+            };
+
+            if hierarchical {
+                quote! { ::fun_time::__private::prefix_path(#record_name, #formatted) }
+            } else {
+                formatted
+            }
+        };
+        let report = |statement: proc_macro2::TokenStream| {
+            if safe_reporting {
+                quote! { ::fun_time::__private::report_safely(#name, || { #statement }); }
+            } else {
+                statement
+            }
+        };
+
         // Store the message at the top of the function because if the function were to take
         // ownership of the argument it would be gone by the time we want to print the done message.
-        let message_statement = quote! {
-            let super_secret_variable_that_does_not_clash_message = format!(#message);
+        let formatted_message = format_message(&message);
+        let message_statement = match args.reporting {
+            // Puffin only formats the message when profiling is turned on, see `scope_statement`
+            #[cfg(feature = "puffin")]
+            Reporting::Puffin => quote! {},
+            _ => quote! {
+                let super_secret_variable_that_does_not_clash_message = #formatted_message;
+            },
         };
 
-        let starting_statement = match args.reporting {
-            Reporting::Println => quote! {
-                println!("{}", super_secret_variable_that_does_not_clash_message);
-            },
+        // When an early return message is given we keep track of calls to `fun_time::early!()`
+        // so we can pick the right message when we are done.
+        let (early_return_statement, done_message) = match args.early_return_message {
+            Some(early_return_message) => (
+                {
+                    let formatted_early_return_message = format_message(&early_return_message);
+                    quote! {
+                        let super_secret_variable_that_does_not_clash_early_message = #formatted_early_return_message;
+                        let super_secret_variable_that_does_not_clash_early_guard = ::fun_time::__private::EarlyReturnGuard::new();
+                    }
+                },
+                quote! {
+                    if super_secret_variable_that_does_not_clash_early_guard.returned_early() {
+                        &super_secret_variable_that_does_not_clash_early_message
+                    } else {
+                        &super_secret_variable_that_does_not_clash_message
+                    }
+                },
+            ),
+            None => (
+                quote! {},
+                quote! { super_secret_variable_that_does_not_clash_message },
+            ),
+        };
+
+        // When respecting the test capture, the messages go to the `stderr` in tests so they are
+        // kept apart from the output of the test itself.
+        let respect_test_capture = args.respect_test_capture;
+        let print = |arguments: proc_macro2::TokenStream| {
+            if respect_test_capture {
+                quote! {
+                    #[cfg(test)]
+                    eprintln!(#arguments);
+                    #[cfg(not(test))]
+                    println!(#arguments);
+                }
+            } else {
+                quote! { println!(#arguments); }
+            }
+        };
+
+        let starting_statement = report(match args.reporting {
+            Reporting::Println => {
+                print(quote! { "{}", super_secret_variable_that_does_not_clash_message })
+            }
             #[cfg(feature = "log")]
             Reporting::Log => quote! {
                 #log_tokens("{}", super_secret_variable_that_does_not_clash_message);
             },
+            #[cfg(feature = "puffin")]
+            Reporting::Puffin => quote! {},
+            #[cfg(feature = "minitrace")]
+            Reporting::Minitrace => quote! {},
+        });
+
+        // The done message is built up from parts, each with their own format arguments
+        let mut done_format = String::from("{}: Done in ");
+        let mut done_args = vec![done_message];
+
+        // With adaptive precision the number of decimals is picked at runtime based on the
+        // magnitude of the elapsed time, otherwise we always show 2 decimals.
+        if args.adaptive_precision {
+            done_format.push_str("{:.*?}");
+            done_args.push(quote! { ::fun_time::__private::adaptive_precision(elapsed) });
+        } else {
+            done_format.push_str("{:.2?}");
+        }
+        done_args.push(quote! { elapsed });
+
+        // Decide whether this is a cold call before running the function, so the call that
+        // started first is the cold one.
+        let cold_statement = if args.mark_cold {
+            done_format.push_str("{}");
+            done_args.push(quote! {
+                if super_secret_variable_that_does_not_clash_cold { " [cold]" } else { "" }
+            });
+
+            quote! {
+                static SUPER_SECRET_VARIABLE_THAT_DOES_NOT_CLASH_WARM: std::sync::atomic::AtomicBool =
+                    std::sync::atomic::AtomicBool::new(false);
+                let super_secret_variable_that_does_not_clash_cold =
+                    ::fun_time::__private::is_cold_call(&SUPER_SECRET_VARIABLE_THAT_DOES_NOT_CLASH_WARM);
+            }
+        } else {
+            quote! {}
         };
 
-        let reporting_statement = match args.reporting {
-            Reporting::Println => quote! {
-                println!("{}: Done in {:.2?}", super_secret_variable_that_does_not_clash_message, elapsed);
-            },
+        // Compare against the running mean of the function, which is updated by this call
+        if args.relative {
+            done_format.push_str(" ({})");
+            done_args.push(quote! {{
+                static SUPER_SECRET_VARIABLE_THAT_DOES_NOT_CLASH_MEAN: std::sync::atomic::AtomicU64 =
+                    std::sync::atomic::AtomicU64::new(0);
+                ::fun_time::__private::relative_to_mean(&SUPER_SECRET_VARIABLE_THAT_DOES_NOT_CLASH_MEAN, elapsed)
+            }});
+        }
+
+        // Compare against the previous call of the function, the first call shows no delta
+        if args.delta {
+            done_format.push_str("{}");
+            done_args.push(quote! {{
+                static SUPER_SECRET_VARIABLE_THAT_DOES_NOT_CLASH_PREVIOUS: std::sync::atomic::AtomicU64 =
+                    std::sync::atomic::AtomicU64::new(u64::MAX);
+                ::fun_time::__private::delta_to_previous(&SUPER_SECRET_VARIABLE_THAT_DOES_NOT_CLASH_PREVIOUS, elapsed)
+            }});
+        }
+
+        if args.epoch_us {
+            done_format.push_str(" epoch_us={}");
+            done_args
+                .push(quote! { ::fun_time::__private::epoch_us(std::time::SystemTime::now()) });
+        }
+
+        let reporting_statement = report(match args.reporting {
+            Reporting::Println => print(quote! { #done_format, #(#done_args),* }),
             #[cfg(feature = "log")]
             Reporting::Log => quote! {
-                #log_tokens("{}: Done in {:.2?}", super_secret_variable_that_does_not_clash_message, elapsed);
+                #log_tokens(#done_format, #(#done_args),*);
             },
+            #[cfg(feature = "puffin")]
+            Reporting::Puffin => quote! {},
+            #[cfg(feature = "minitrace")]
+            Reporting::Minitrace => quote! {},
+        });
+
+        // Profilers measure the time themselves, they just need a scope around the function body
+        let scope_statement = match args.reporting {
+            #[cfg(feature = "puffin")]
+            Reporting::Puffin => quote! {
+                ::fun_time::__private::puffin::profile_scope!(#name, #formatted_message);
+            },
+            #[cfg(feature = "minitrace")]
+            Reporting::Minitrace => {
+                let message_property = if has_message {
+                    quote! {
+                        .with_property(|| ("message", super_secret_variable_that_does_not_clash_message.clone()))
+                    }
+                } else {
+                    quote! {}
+                };
+
+                let parameters: Vec<&syn::Ident> = signature
+                    .inputs
+                    .iter()
+                    .filter_map(|input| match input {
+                        syn::FnArg::Typed(pat_type) => match pat_type.pat.as_ref() {
+                            syn::Pat::Ident(pat_ident) => Some(&pat_ident.ident),
+                            _ => None,
+                        },
+                        syn::FnArg::Receiver(_) => None,
+                    })
+                    .collect();
+                let key = |parameter: &syn::Ident| {
+                    parameter.to_string().trim_start_matches("r#").to_string()
+                };
+
+                let skipped: Vec<&str> = args
+                    .skip_properties
+                    .as_deref()
+                    .unwrap_or_default()
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|skipped| !skipped.is_empty())
+                    .collect();
+                if let Some(unknown) = skipped.iter().find(|skipped| {
+                    !parameters
+                        .iter()
+                        .any(|parameter| key(parameter) == **skipped)
+                }) {
+                    let error = format!("the `skip_properties` attribute contains `{unknown}`, which is not a parameter of the function!");
+                    return make_compile_error!(#error);
+                }
+
+                // Every other parameter bound to a plain identifier becomes a property, using its
+                // `Debug` output. Parameters starting with an underscore are unused, so skipped too.
+                let parameter_properties = parameters.into_iter().filter(|parameter| {
+                    let key = key(parameter);
+                    !key.starts_with('_') && !skipped.contains(&key.as_str())
+                }).map(|parameter| {
+                    let key = key(parameter);
+                    let value = if safe_reporting {
+                        quote! {
+                            ::fun_time::__private::report_safely(#name, || format!("{:?}", #parameter))
+                                .unwrap_or_default()
+                        }
+                    } else {
+                        quote! { format!("{:?}", #parameter) }
+                    };
+
+                    quote! { .with_property(|| (#key, #value)) }
+                });
+
+                quote! {
+                    let _super_secret_variable_that_does_not_clash_span =
+                        ::fun_time::__private::minitrace::local::LocalSpan::enter_with_local_parent(#name)
+                            #message_property
+                            #(#parameter_properties)*;
+                }
+            }
+            _ => quote! {},
         };
 
         quote! {
             #visibility #signature {
+                #hierarchy_statement
                 #message_statement
+                #early_return_statement
+                #cold_statement
                 #starting_statement
+                #scope_statement
 
                 #wrapped_block
 
@@ -319,5 +869,74 @@ pub fn fun_time(
         }
     };
 
+    let tokens = if args.last_elapsed {
+        let note = format!(
+            "\n\nThis function is timed, the elapsed time of its last call is available through `fun_time::last_elapsed(\"{fn_ident}\")`."
+        );
+        quote! {
+            #[doc = #note]
+            #tokens
+        }
+    } else {
+        tokens
+    };
+
+    if args.expand_debug {
+        return with_expansion(tokens, &visibility, &fn_ident).into();
+    }
+
     tokens.into()
 }
+
+/// Add the string constant with the pretty-printed `tokens` for the `expand_debug` attribute.
+fn with_expansion(
+    tokens: proc_macro2::TokenStream,
+    visibility: &syn::Visibility,
+    fn_ident: &syn::Ident,
+) -> proc_macro2::TokenStream {
+    let expansion = pretty_print(&tokens);
+    let expansion_ident = format_ident!(
+        "{}_FUN_TIME_EXPANSION",
+        fn_ident.to_string().trim_start_matches("r#").to_uppercase()
+    );
+
+    quote! {
+        #tokens
+
+        #[allow(dead_code)]
+        #visibility const #expansion_ident: &str = #expansion;
+    }
+}
+
+/// Parse a budget like `"10ms"` or `"1.5s"` into a number of nanoseconds.
+fn parse_budget(budget: &str) -> Result<u64, String> {
+    let unsupported = || {
+        format!(
+            "Unsupported value for `static_budget` attribute: {budget}. Use a number followed by one of: ns, us, µs, ms, s"
+        )
+    };
+
+    let split_at = budget
+        .find(|character: char| !character.is_ascii_digit() && character != '.')
+        .ok_or_else(unsupported)?;
+    let (amount, unit) = budget.split_at(split_at);
+    let amount: f64 = amount.parse().map_err(|_| unsupported())?;
+    let nanos_per_unit = match unit.trim() {
+        "ns" => 1.0,
+        "us" | "µs" => 1_000.0,
+        "ms" => 1_000_000.0,
+        "s" => 1_000_000_000.0,
+        _ => return Err(unsupported()),
+    };
+
+    Ok((amount * nanos_per_unit).round() as u64)
+}
+
+/// Pretty-print the generated tokens, falling back to the plain token string if they can not be
+/// parsed as a file.
+fn pretty_print(tokens: &proc_macro2::TokenStream) -> String {
+    match syn::parse2::<syn::File>(tokens.clone()) {
+        Ok(file) => prettyplease::unparse(&file),
+        Err(_) => tokens.to_string(),
+    }
+}
@@ -0,0 +1,235 @@
+//! Support code for the code generated by the `fun_time` attribute, not part of the public API.
+
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Mutex, MutexGuard, Once, OnceLock};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Re-exported so the generated profiling scopes do not require a direct dependency on `puffin`.
+#[cfg(feature = "puffin")]
+pub use puffin;
+
+/// Re-exported so the generated spans do not require a direct dependency on `minitrace`.
+#[cfg(feature = "minitrace")]
+pub use minitrace;
+
+thread_local! {
+    /// Whether the currently running timed function signalled an early return.
+    static RETURNED_EARLY: Cell<bool> = const { Cell::new(false) };
+    /// The names of the currently running hierarchical timed functions, outermost first.
+    static HIERARCHY: RefCell<Vec<&'static str>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Signal that the currently running timed function is about to return early.
+pub fn mark_early_return() {
+    RETURNED_EARLY.with(|returned_early| returned_early.set(true));
+}
+
+/// Keeps track of early returns for a single call of a timed function.
+///
+/// Creating the guard resets the flag, and dropping it restores the flag of the enclosing timed
+/// function, so nested timed functions do not influence each other.
+pub struct EarlyReturnGuard {
+    previous: bool,
+}
+
+impl EarlyReturnGuard {
+    #[allow(clippy::new_without_default)]
+    pub fn new() -> Self {
+        Self {
+            previous: RETURNED_EARLY.with(|returned_early| returned_early.replace(false)),
+        }
+    }
+
+    pub fn returned_early(&self) -> bool {
+        RETURNED_EARLY.with(Cell::get)
+    }
+}
+
+impl Drop for EarlyReturnGuard {
+    fn drop(&mut self) {
+        RETURNED_EARLY.with(|returned_early| returned_early.set(self.previous));
+    }
+}
+
+/// Pick the number of decimals to show for the given elapsed time, matching the unit that the
+/// debug format of [`Duration`] uses for it.
+pub fn adaptive_precision(elapsed: Duration) -> usize {
+    if elapsed < Duration::from_micros(1) {
+        0
+    } else if elapsed < Duration::from_millis(1) {
+        3
+    } else if elapsed < Duration::from_secs(1) {
+        2
+    } else {
+        1
+    }
+}
+
+/// Run the given reporting code, catching any panic so it can not take down the timed function.
+///
+/// Returns `None` and prints a fallback message to the `stderr` if the reporting code panicked.
+pub fn report_safely<T>(name: &str, report: impl FnOnce() -> T) -> Option<T> {
+    match std::panic::catch_unwind(std::panic::AssertUnwindSafe(report)) {
+        Ok(reported) => Some(reported),
+        Err(_) => {
+            eprintln!("fun_time: reporting the execution time of `{name}` panicked");
+            None
+        }
+    }
+}
+
+/// Check whether this is the first, cold, call of a function, given its own `warm` flag.
+pub fn is_cold_call(warm: &AtomicBool) -> bool {
+    // Only the very first call has to pay for the swap
+    !warm.load(Ordering::Relaxed) && !warm.swap(true, Ordering::Relaxed)
+}
+
+/// The weight of a single call in the running mean of [`relative_to_mean`].
+const MEAN_WEIGHT: f64 = 0.1;
+
+/// Update the running mean of a function, stored as the bits of an `f64` number of nanoseconds,
+/// and describe how the elapsed time relates to the mean before this call.
+pub fn relative_to_mean(mean: &AtomicU64, elapsed: Duration) -> String {
+    let elapsed_nanos = elapsed.as_nanos() as f64;
+
+    let update = mean.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |previous_bits| {
+        let previous_mean = f64::from_bits(previous_bits);
+        let new_mean = if previous_bits == 0 {
+            elapsed_nanos
+        } else {
+            previous_mean + MEAN_WEIGHT * (elapsed_nanos - previous_mean)
+        };
+
+        Some(new_mean.to_bits())
+    });
+    let (Ok(previous_bits) | Err(previous_bits)) = update;
+    let previous_mean = f64::from_bits(previous_bits);
+
+    if previous_mean > 0.0 {
+        format!("{:.1}x mean", elapsed_nanos / previous_mean)
+    } else {
+        "n/a mean".to_string()
+    }
+}
+
+/// Keeps track of the path of a single call of a hierarchical timed function.
+///
+/// Entering pushes the name of the function on the hierarchy of the current thread, and dropping
+/// the guard pops it again, also when the function panics.
+pub struct HierarchyGuard {
+    path: String,
+}
+
+impl HierarchyGuard {
+    pub fn enter(name: &'static str) -> Self {
+        let path = HIERARCHY.with(|hierarchy| {
+            let mut hierarchy = hierarchy.borrow_mut();
+            hierarchy.push(name);
+            hierarchy.join("/")
+        });
+
+        Self { path }
+    }
+
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+}
+
+impl Drop for HierarchyGuard {
+    fn drop(&mut self) {
+        HIERARCHY.with(|hierarchy| hierarchy.borrow_mut().pop());
+    }
+}
+
+/// Prefix the message of a hierarchical timed function with its path.
+pub fn prefix_path(path: &str, message: String) -> String {
+    if message.is_empty() {
+        path.to_string()
+    } else {
+        format!("{path}: {message}")
+    }
+}
+
+fn last_elapsed_registry() -> MutexGuard<'static, HashMap<&'static str, &'static LastElapsed>> {
+    static REGISTRY: OnceLock<Mutex<HashMap<&'static str, &'static LastElapsed>>> = OnceLock::new();
+
+    REGISTRY
+        .get_or_init(Default::default)
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// The elapsed time of the last call of a function, stored in a `static` of that function.
+pub struct LastElapsed {
+    name: &'static str,
+    /// The elapsed time in nanoseconds, or `u64::MAX` if the function was not called yet.
+    nanos: AtomicU64,
+    registered: Once,
+}
+
+impl LastElapsed {
+    pub const fn new(name: &'static str) -> Self {
+        Self {
+            name,
+            nanos: AtomicU64::new(u64::MAX),
+            registered: Once::new(),
+        }
+    }
+
+    pub fn store(&'static self, elapsed: Duration) {
+        let nanos = u64::try_from(elapsed.as_nanos()).unwrap_or(u64::MAX - 1);
+        self.nanos.store(nanos, Ordering::Relaxed);
+
+        // Only the first call has to register the function
+        self.registered.call_once(|| {
+            last_elapsed_registry().insert(self.name, self);
+        });
+    }
+
+    fn load(&self) -> Option<Duration> {
+        match self.nanos.load(Ordering::Relaxed) {
+            u64::MAX => None,
+            nanos => Some(Duration::from_nanos(nanos)),
+        }
+    }
+}
+
+/// See [`crate::last_elapsed`].
+pub fn last_elapsed(name: &str) -> Option<Duration> {
+    let last_elapsed = *last_elapsed_registry().get(name)?;
+
+    last_elapsed.load()
+}
+
+/// The number of microseconds since the Unix epoch, negative if the given time is before it.
+pub fn epoch_us(time: SystemTime) -> i128 {
+    match time.duration_since(UNIX_EPOCH) {
+        Ok(since_epoch) => since_epoch.as_micros() as i128,
+        Err(error) => -(error.duration().as_micros() as i128),
+    }
+}
+
+/// Store the elapsed time as the previous elapsed time of a function, stored in nanoseconds or
+/// `u64::MAX` if there was no previous call, and describe the difference with the previous call.
+pub fn delta_to_previous(previous: &AtomicU64, elapsed: Duration) -> String {
+    let nanos = u64::try_from(elapsed.as_nanos()).unwrap_or(u64::MAX - 1);
+
+    match previous.swap(nanos, Ordering::Relaxed) {
+        u64::MAX => String::new(),
+        previous_nanos if nanos >= previous_nanos => {
+            format!(" (Δ +{:.2?})", Duration::from_nanos(nanos - previous_nanos))
+        }
+        previous_nanos => format!(" (Δ -{:.2?})", Duration::from_nanos(previous_nanos - nanos)),
+    }
+}
+
+/// Count a call of a function, given its own call counter, and check whether it is past the
+/// given number of warmup calls.
+pub fn is_warmed_up(calls: &AtomicU64, warmup_calls: u64) -> bool {
+    // Once warmed up the counter is no longer touched, so it can not overflow
+    calls.load(Ordering::Relaxed) >= warmup_calls
+        || calls.fetch_add(1, Ordering::Relaxed) >= warmup_calls
+}
@@ -0,0 +1,124 @@
+//! Appending the accumulated timing events to a CSV file, to analyse and compare runs.
+
+use std::fs::{File, OpenOptions};
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, MutexGuard};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// How often the buffered events are written to the file by the flusher thread.
+const FLUSH_INTERVAL: Duration = Duration::from_secs(1);
+
+static LOG: Mutex<Option<BufWriter<File>>> = Mutex::new(None);
+/// Whether a log is open, so appending does not have to lock [`LOG`] when none is.
+static IS_OPEN: AtomicBool = AtomicBool::new(false);
+/// Whether the flusher thread has been started, it keeps running once it is.
+static IS_FLUSHING: AtomicBool = AtomicBool::new(false);
+
+fn log() -> MutexGuard<'static, Option<BufWriter<File>>> {
+    LOG.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Start appending every timing event that is accumulated, see [`crate::record`], to the CSV file
+/// at the given path.
+///
+/// Only the calls that are recorded end up in the log, so those of functions with the `accumulate`
+/// or `static_budget` attribute, [`crate::Stopwatch`] and the FFI timers. Calls of functions that
+/// are only reported are not logged.
+///
+/// The file is created with a `timestamp_us,fn,elapsed_ns` header if it does not exist yet, where
+/// `timestamp_us` is the number of microseconds since the Unix epoch at which the event was
+/// recorded. Events are buffered and written to the file every second by a background thread, use
+/// [`flush_log`] or [`close_log`] to write them immediately. The log is never closed on its own,
+/// so call [`close_log`] before your program exits, otherwise the events of the last second are
+/// lost. Opening a log while another one is open closes the other one.
+pub fn open_log(path: impl AsRef<Path>) -> std::io::Result<()> {
+    let file = OpenOptions::new().create(true).append(true).open(path)?;
+    let is_new = file.metadata()?.len() == 0;
+
+    let mut writer = BufWriter::new(file);
+    if is_new {
+        writeln!(writer, "timestamp_us,fn,elapsed_ns")?;
+    }
+
+    if !IS_FLUSHING.swap(true, Ordering::Relaxed) {
+        let spawned = std::thread::Builder::new()
+            .name(String::from("fun_time_csv_log"))
+            .spawn(|| loop {
+                std::thread::sleep(FLUSH_INTERVAL);
+                // Errors are kept in the writer, and returned by the next flush or close
+                let _ = flush_log();
+            });
+
+        if let Err(error) = spawned {
+            IS_FLUSHING.store(false, Ordering::Relaxed);
+            return Err(error);
+        }
+    }
+
+    let mut log = log();
+    IS_OPEN.store(true, Ordering::Relaxed);
+    if let Some(mut previous) = log.replace(writer) {
+        previous.flush()?;
+    }
+
+    Ok(())
+}
+
+/// Write the buffered events to the open CSV file, if any.
+pub fn flush_log() -> std::io::Result<()> {
+    if !IS_OPEN.load(Ordering::Relaxed) {
+        return Ok(());
+    }
+
+    match log().as_mut() {
+        Some(writer) => writer.flush(),
+        None => Ok(()),
+    }
+}
+
+/// Write the buffered events to the open CSV file, if any, and stop appending events to it.
+pub fn close_log() -> std::io::Result<()> {
+    let mut log = log();
+    IS_OPEN.store(false, Ordering::Relaxed);
+    match log.take() {
+        Some(mut writer) => writer.flush(),
+        None => Ok(()),
+    }
+}
+
+/// Append a timing event to the open CSV file, if any.
+///
+/// Errors can not be reported here, so they are ignored. Errors while writing the buffered events
+/// are returned by the next [`flush_log`] or [`close_log`].
+pub(crate) fn append(name: &str, elapsed: Duration) {
+    if !IS_OPEN.load(Ordering::Relaxed) {
+        return;
+    }
+
+    let mut log = log();
+    let Some(writer) = log.as_mut() else {
+        return;
+    };
+
+    let timestamp_us = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |since_epoch| since_epoch.as_micros());
+
+    let _ = writeln!(
+        writer,
+        "{timestamp_us},{},{}",
+        escape_field(name),
+        elapsed.as_nanos()
+    );
+}
+
+/// Quote a field if it contains characters that have a special meaning in CSV.
+fn escape_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
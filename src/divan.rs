@@ -0,0 +1,74 @@
+//! Reusing functions using the `give_back` attribute in [divan](https://crates.io/crates/divan)
+//! benchmarks.
+//!
+//! Divan measures the benchmarked code itself, so the durations given back by the function are
+//! collected next to it in [`Samples`], for example to compare them with the divan results or to
+//! check the timing of a specific part of the function.
+//!
+//! ```
+//! use fun_time::divan::Samples;
+//! use fun_time::fun_time;
+//!
+//! #[fun_time(give_back)]
+//! fn parse(input: &str) -> Vec<u32> {
+//!     input.split(',').map(|number| number.parse().unwrap()).collect()
+//! }
+//!
+//! static PARSE_SAMPLES: Samples = Samples::new();
+//!
+//! #[divan::bench]
+//! fn bench_parse(bencher: divan::Bencher) {
+//!     PARSE_SAMPLES.bench(bencher, || parse("1,2,3,4,5"));
+//! }
+//!
+//! fn main() {
+//!     divan::main();
+//!
+//!     let durations = PARSE_SAMPLES.durations();
+//!     assert!(!durations.is_empty());
+//!     println!("fun_time measured {} samples", durations.len());
+//! }
+//! ```
+
+use std::sync::{Mutex, MutexGuard};
+use std::time::Duration;
+
+/// Collects the durations given back by a function using the `give_back` attribute.
+#[derive(Debug, Default)]
+pub struct Samples {
+    durations: Mutex<Vec<Duration>>,
+}
+
+impl Samples {
+    pub const fn new() -> Self {
+        Self {
+            durations: Mutex::new(Vec::new()),
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Vec<Duration>> {
+        self.durations
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Collect the duration given back by a function and return its original return value.
+    pub fn record<T>(&self, (value, elapsed): (T, Duration)) -> T {
+        self.lock().push(elapsed);
+        value
+    }
+
+    /// Benchmark a function using the `give_back` attribute with divan, and collect the durations
+    /// it gives back.
+    pub fn bench<T, F>(&self, bencher: ::divan::Bencher, give_back: F)
+    where
+        F: Fn() -> (T, Duration) + Sync,
+    {
+        bencher.bench(|| self.record(give_back()));
+    }
+
+    /// The collected durations, in the order they were recorded.
+    pub fn durations(&self) -> Vec<Duration> {
+        self.lock().clone()
+    }
+}
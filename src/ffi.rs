@@ -0,0 +1,82 @@
+//! C API to time code across an FFI boundary.
+//!
+//! Enable the `ffi` feature to export the following functions from your Rust library, so C code
+//! that is linked together with it can time its own work:
+//!
+//! ```c
+//! #include <stdint.h>
+//!
+//! // Start timing, returns a handle to pass to `fun_time_end`, or 0 if `label` is NULL.
+//! uint64_t fun_time_begin(const char *label);
+//!
+//! // Stop timing, report "LABEL: Done in ELAPSED_TIME" and accumulate the elapsed time under the
+//! // label, returns the elapsed time in nanoseconds, or 0 if the handle is unknown.
+//! uint64_t fun_time_end(uint64_t handle);
+//! ```
+//!
+//! The label is copied, so it does not have to outlive the call to `fun_time_begin`. Handles can
+//! be ended from any thread, but only once.
+
+use std::collections::HashMap;
+use std::ffi::{c_char, CStr};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::Instant;
+
+/// Handle `0` is never handed out, so C code can use it as a sentinel value.
+static NEXT_HANDLE: AtomicU64 = AtomicU64::new(1);
+
+fn running() -> &'static Mutex<HashMap<u64, (String, Instant)>> {
+    static RUNNING: OnceLock<Mutex<HashMap<u64, (String, Instant)>>> = OnceLock::new();
+
+    RUNNING.get_or_init(Default::default)
+}
+
+/// Start timing under the given label and return a handle for [`fun_time_end`].
+///
+/// Returns `0` if `label` is a null pointer.
+///
+/// # Safety
+///
+/// `label` must either be null or point to a valid nul-terminated C string.
+#[no_mangle]
+pub unsafe extern "C" fn fun_time_begin(label: *const c_char) -> u64 {
+    if label.is_null() {
+        return 0;
+    }
+
+    let label = CStr::from_ptr(label).to_string_lossy().into_owned();
+    let handle = NEXT_HANDLE.fetch_add(1, Ordering::Relaxed);
+
+    running()
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .insert(handle, (label, Instant::now()));
+
+    handle
+}
+
+/// Stop timing the given handle, report and accumulate the elapsed time and return it in
+/// nanoseconds.
+///
+/// Returns `0` if the handle is unknown, for example because it was already ended.
+#[no_mangle]
+pub extern "C" fn fun_time_end(handle: u64) -> u64 {
+    let Some((label, start)) = running()
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .remove(&handle)
+    else {
+        return 0;
+    };
+
+    let elapsed = start.elapsed();
+    crate::record(&label, elapsed);
+
+    #[cfg(not(feature = "log"))]
+    println!("{}: Done in {:.2?}", label, elapsed);
+    #[cfg(feature = "log")]
+    log::info!("{}: Done in {:.2?}", label, elapsed);
+
+    u64::try_from(elapsed.as_nanos()).unwrap_or(u64::MAX)
+}
@@ -1,5 +1,74 @@
 pub use fun_time_derive::*;
 
+// Lets the code generated by the `fun_time` attribute refer to `::fun_time` inside this crate too.
+extern crate self as fun_time;
+
+#[doc(hidden)]
+pub mod __private;
+
+#[cfg(feature = "divan")]
+pub mod divan;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+
+mod csv_log;
+#[cfg(feature = "prometheus-text")]
+mod prometheus;
+mod stopwatch;
+mod totals;
+
+pub use csv_log::{close_log, flush_log, open_log};
+#[cfg(feature = "prometheus-text")]
+pub use prometheus::prometheus_metrics;
+pub use stopwatch::Stopwatch;
+pub use totals::{
+    budget_violations, record, record_with_budget, report_totals, reset_totals, totals,
+    totals_report, BudgetViolation, Totals, SLOWEST_CALLS_KEPT,
+};
+
+/// Get the elapsed time of the last call of a function using the `last_elapsed` attribute.
+///
+/// Returns `None` if the function with the given name was not called yet.
+///
+/// ```
+/// use fun_time::fun_time;
+///
+/// #[fun_time(message = "saving", last_elapsed)]
+/// fn save() {}
+///
+/// save();
+///
+/// assert!(fun_time::last_elapsed("save").is_some());
+/// ```
+pub fn last_elapsed(name: &str) -> Option<std::time::Duration> {
+    __private::last_elapsed(name)
+}
+
+/// Marks an early return in a function using the `early_return_message` attribute.
+///
+/// Call this right before the `return` statement, so the done message uses the
+/// `early_return_message` instead of the regular `message`.
+///
+/// ```
+/// use fun_time::fun_time;
+///
+/// #[fun_time(message = "parsing {input}", early_return_message = "parsing {input} (empty)")]
+/// fn parse(input: &str) -> Vec<String> {
+///     if input.is_empty() {
+///         fun_time::early!();
+///         return Vec::new();
+///     }
+///
+///     input.split(',').map(String::from).collect()
+/// }
+/// ```
+#[macro_export]
+macro_rules! early {
+    () => {
+        $crate::__private::mark_early_return()
+    };
+}
+
 #[cfg(test)]
 mod tests {
     use fun_time_derive::fun_time;
@@ -84,11 +153,710 @@ mod tests {
         let _ = enum_b.get_trait_item().speak();
     }
 
+    #[test]
+    fn works_with_qualified_self_return_type_in_give_back() {
+        trait Producer {
+            type Output;
+
+            fn produce(&self) -> Self::Output;
+        }
+
+        struct Doubler {
+            value: i32,
+        }
+
+        impl Producer for Doubler {
+            type Output = i32;
+
+            fn produce(&self) -> Self::Output {
+                self.value * 2
+            }
+        }
+
+        impl Doubler {
+            #[fun_time(give_back)]
+            fn produce_timed(&self) -> <Self as Producer>::Output {
+                self.produce()
+            }
+        }
+
+        let (produced, _elapsed_time) = Doubler { value: 21 }.produce_timed();
+        assert_eq!(42, produced);
+    }
+
+    #[fun_time(accumulate)]
+    fn called_at_cadence() {}
+
+    #[test]
+    fn it_reports_the_call_rate() {
+        for _ in 0..5 {
+            called_at_cadence();
+            std::thread::sleep(Duration::from_millis(20));
+        }
+
+        // 4 intervals of at least 20ms, so at most 50 calls per second
+        let rate = totals_for("called_at_cadence").rate().unwrap();
+        assert!(rate > 10.0 && rate <= 50.0, "unexpected rate: {rate}");
+
+        let report = crate::totals_report();
+        let line = report
+            .lines()
+            .find(|line| line.starts_with("called_at_cadence: 5 calls"))
+            .unwrap();
+        assert!(line.contains(&format!(", rate {rate:.2}/s")));
+    }
+
+    #[fun_time(static_budget = "1ms")]
+    fn over_budget() {
+        std::thread::sleep(Duration::from_millis(5));
+    }
+
+    #[fun_time(static_budget = "1.5s")]
+    fn within_budget() {}
+
+    #[test]
+    fn it_reports_budget_violations() {
+        over_budget();
+        within_budget();
+
+        assert_eq!(
+            Some(Duration::from_millis(1)),
+            totals_for("over_budget").budget
+        );
+        assert_eq!(
+            Some(Duration::from_millis(1500)),
+            totals_for("within_budget").budget
+        );
+
+        let violations = crate::report_totals().unwrap_err();
+        let violation = violations
+            .iter()
+            .find(|violation| violation.name == "over_budget")
+            .unwrap();
+        assert!(violation.mean >= Duration::from_millis(5));
+        assert_eq!(Duration::from_millis(1), violation.budget);
+        assert!(!violations
+            .iter()
+            .any(|violation| violation.name == "within_budget"));
+    }
+
+    #[test]
+    #[should_panic(expected = "over_budget: mean")]
+    fn budget_violations_can_fail_a_test() {
+        over_budget();
+
+        if let Err(violations) = crate::report_totals() {
+            panic!("{}", violations[0]);
+        }
+    }
+
+    #[fun_time(hierarchical, accumulate)]
+    fn hierarchy_outer() {
+        hierarchy_inner();
+    }
+
+    #[fun_time(hierarchical, accumulate)]
+    fn hierarchy_inner() {
+        not_hierarchical();
+    }
+
+    fn not_hierarchical() {
+        let (_leaf, _elapsed_time) = hierarchy_leaf();
+    }
+
+    #[fun_time(hierarchical, accumulate, give_back)]
+    fn hierarchy_leaf() -> &'static str {
+        "leaf"
+    }
+
+    #[test]
+    fn it_accumulates_under_hierarchical_names() {
+        hierarchy_outer();
+        hierarchy_leaf();
+
+        assert_eq!(1, totals_for("hierarchy_outer").count);
+        assert_eq!(1, totals_for("hierarchy_outer/hierarchy_inner").count);
+        assert_eq!(
+            1,
+            totals_for("hierarchy_outer/hierarchy_inner/hierarchy_leaf").count
+        );
+        assert_eq!(1, totals_for("hierarchy_leaf").count);
+    }
+
+    #[test]
+    fn it_appends_events_to_csv_log() {
+        let path = std::env::temp_dir().join(format!(
+            "fun_time_appends_events_to_csv_log_{}.csv",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_file(&path);
+
+        crate::open_log(&path).unwrap();
+        crate::record("appended_to_csv", Duration::from_nanos(1_500));
+        crate::record("appended, \"quoted\"", Duration::from_micros(2));
+        crate::record("appended_to_csv", Duration::from_millis(3));
+
+        // Written by the flusher thread without closing the log
+        std::thread::sleep(Duration::from_millis(1_500));
+        assert!(std::fs::read_to_string(&path)
+            .unwrap()
+            .contains(",appended_to_csv,3000000"));
+
+        crate::close_log().unwrap();
+
+        // Not appended after closing
+        crate::record("appended_to_csv", Duration::from_millis(4));
+
+        let contents = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let mut lines = contents.lines();
+        assert_eq!(Some("timestamp_us,fn,elapsed_ns"), lines.next());
+
+        // Other tests may record events at the same time, so only look at our own
+        let rows: Vec<(&str, u128)> = lines
+            .map(|line| {
+                let (timestamp_us, rest) = line.split_once(',').unwrap();
+                let (name, elapsed_ns) = rest.rsplit_once(',').unwrap();
+                assert!(timestamp_us.parse::<u128>().unwrap() > 0);
+                (name, elapsed_ns.parse().unwrap())
+            })
+            .filter(|(name, _)| name.contains("appended"))
+            .collect();
+
+        assert_eq!(
+            vec![
+                ("appended_to_csv", 1_500),
+                ("\"appended, \"\"quoted\"\"\"", 2_000),
+                ("appended_to_csv", 3_000_000),
+            ],
+            rows
+        );
+    }
+
+    #[fun_time(message = "sleeping", last_elapsed)]
+    fn keeps_last_elapsed(millis: u64) -> u64 {
+        std::thread::sleep(Duration::from_millis(millis));
+        millis
+    }
+
+    #[test]
+    fn it_keeps_the_last_elapsed_time() {
+        assert_eq!(None, crate::last_elapsed("keeps_last_elapsed"));
+
+        assert_eq!(20, keeps_last_elapsed(20));
+        let first = crate::last_elapsed("keeps_last_elapsed").unwrap();
+        assert!(first >= Duration::from_millis(20));
+
+        assert_eq!(1, keeps_last_elapsed(1));
+        let second = crate::last_elapsed("keeps_last_elapsed").unwrap();
+        assert!(second >= Duration::from_millis(1) && second < first);
+    }
+
+    #[test]
+    fn stopwatch_accumulates_intervals() {
+        use crate::Stopwatch;
+
+        let name = "stopwatch_accumulates_intervals";
+
+        // Unbalanced calls are ignored
+        assert_eq!(None, Stopwatch::lap(name));
+        assert_eq!(None, Stopwatch::stop(name));
+
+        Stopwatch::start(name);
+        std::thread::sleep(Duration::from_millis(5));
+        let lap = Stopwatch::lap(name).unwrap();
+        std::thread::sleep(Duration::from_millis(10));
+        let stop = Stopwatch::stop(name).unwrap();
+
+        assert!(lap >= Duration::from_millis(5));
+        assert!(stop >= Duration::from_millis(10));
+        assert_eq!(None, Stopwatch::stop(name));
+
+        let totals = totals_for(name);
+        assert_eq!(2, totals.count);
+        assert_eq!(lap + stop, totals.total);
+        // The sleeps only give lower bounds, so the lap may turn out to be the slowest
+        let mut expected = vec![lap, stop];
+        expected.sort_unstable_by(|a, b| b.cmp(a));
+        assert_eq!(expected, totals.slowest());
+    }
+
+    #[test]
+    fn adaptive_precision_depends_on_magnitude() {
+        let format = |elapsed: Duration| {
+            format!(
+                "{:.*?}",
+                crate::__private::adaptive_precision(elapsed),
+                elapsed
+            )
+        };
+
+        assert_eq!("987ns", format(Duration::from_nanos(987)));
+        assert_eq!("12.345µs", format(Duration::from_nanos(12_345)));
+        assert_eq!("12.35ms", format(Duration::from_micros(12_346)));
+        assert_eq!("12.3s", format(Duration::from_millis(12_345)));
+    }
+
+    struct PanicsWhenFormatted;
+
+    impl Debug for PanicsWhenFormatted {
+        fn fmt(&self, _f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            panic!("this formatter is broken");
+        }
+    }
+
+    #[fun_time(message = "formatting {_broken:?}", safe_reporting = true)]
+    fn formats_badly(_broken: PanicsWhenFormatted) -> &'static str {
+        "still returned"
+    }
+
+    #[test]
+    fn safe_reporting_survives_panicking_formatter() {
+        assert_eq!("still returned", formats_badly(PanicsWhenFormatted));
+    }
+
+    #[test]
+    fn relative_to_mean_reflects_spikes() {
+        use crate::__private::relative_to_mean;
+        use std::sync::atomic::AtomicU64;
+
+        let mean = AtomicU64::new(0);
+
+        assert_eq!(
+            "n/a mean",
+            relative_to_mean(&mean, Duration::from_millis(10))
+        );
+        for _ in 0..10 {
+            assert_eq!(
+                "1.0x mean",
+                relative_to_mean(&mean, Duration::from_millis(10))
+            );
+        }
+        assert_eq!(
+            "3.0x mean",
+            relative_to_mean(&mean, Duration::from_millis(30))
+        );
+        // The spike raised the mean to 12ms
+        assert_eq!(
+            "0.8x mean",
+            relative_to_mean(&mean, Duration::from_millis(10))
+        );
+    }
+
+    #[fun_time(
+        message = "capturing",
+        reporting = "println",
+        respect_test_capture = true
+    )]
+    fn respects_test_capture() {
+        println!("output of the test itself");
+    }
+
+    #[test]
+    #[ignore = "run by `respect_test_capture_keeps_timing_in_captured_output`"]
+    fn respect_test_capture_helper() {
+        respects_test_capture();
+    }
+
+    #[test]
+    fn respect_test_capture_keeps_timing_in_captured_output() {
+        let run_helper = |extra_argument: &str| {
+            std::process::Command::new(std::env::current_exe().unwrap())
+                .args([
+                    "tests::respect_test_capture_helper",
+                    "--exact",
+                    "--ignored",
+                    extra_argument,
+                ])
+                .output()
+                .unwrap()
+        };
+
+        // Captured by the test harness, and shown as part of the output of the test
+        let captured = run_helper("--show-output");
+        let stdout = String::from_utf8_lossy(&captured.stdout);
+        let (_, captured_output) = stdout
+            .split_once("---- tests::respect_test_capture_helper stdout ----")
+            .unwrap();
+        assert!(captured_output.contains("capturing: Done in "));
+        assert!(captured_output.contains("output of the test itself"));
+
+        // Not captured, the timing messages are kept apart from the output of the test
+        let not_captured = run_helper("--nocapture");
+        let stdout = String::from_utf8_lossy(&not_captured.stdout);
+        let stderr = String::from_utf8_lossy(&not_captured.stderr);
+        assert!(stdout.contains("output of the test itself"));
+        assert!(!stdout.contains("capturing"));
+        assert!(stderr.contains("capturing: Done in "));
+    }
+
+    #[fun_time(give_back)]
+    fn borrow_or_own<'a>(s: &'a str, own: bool) -> std::borrow::Cow<'a, str> {
+        if own {
+            std::borrow::Cow::Owned(s.to_uppercase())
+        } else {
+            std::borrow::Cow::Borrowed(s)
+        }
+    }
+
+    #[test]
+    fn works_with_lifetime_parameterized_return_type_in_give_back() {
+        use std::borrow::Cow;
+
+        let input = String::from("hello");
+
+        let (borrowed, _elapsed_time) = borrow_or_own(&input, false);
+        assert!(matches!(borrowed, Cow::Borrowed("hello")));
+
+        let (owned, _elapsed_time) = borrow_or_own(&input, true);
+        assert!(matches!(owned, Cow::Owned(ref value) if value == "HELLO"));
+    }
+
+    #[test]
+    fn epoch_us_handles_time_before_epoch() {
+        use crate::__private::epoch_us;
+        use std::time::{SystemTime, UNIX_EPOCH};
+
+        assert_eq!(0, epoch_us(UNIX_EPOCH));
+        assert_eq!(
+            1_700_000_000_000_000,
+            epoch_us(UNIX_EPOCH + Duration::from_secs(1_700_000_000))
+        );
+        assert_eq!(-5, epoch_us(UNIX_EPOCH - Duration::from_micros(5)));
+        assert!(epoch_us(SystemTime::now()) > 1_700_000_000_000_000);
+    }
+
+    #[test]
+    fn delta_to_previous_reflects_successive_durations() {
+        use crate::__private::delta_to_previous;
+        use std::sync::atomic::AtomicU64;
+
+        let previous = AtomicU64::new(u64::MAX);
+
+        assert_eq!("", delta_to_previous(&previous, Duration::from_millis(10)));
+        assert_eq!(
+            " (Δ +3.00ms)",
+            delta_to_previous(&previous, Duration::from_millis(13))
+        );
+        assert_eq!(
+            " (Δ -12.50ms)",
+            delta_to_previous(&previous, Duration::from_micros(500))
+        );
+        assert_eq!(
+            " (Δ +0.00ns)",
+            delta_to_previous(&previous, Duration::from_micros(500))
+        );
+    }
+
+    #[fun_time(message = "expanding", expand_debug = true)]
+    fn expanded() -> u8 {
+        42
+    }
+
+    #[test]
+    fn it_makes_expansion_available() {
+        assert_eq!(42, expanded());
+
+        assert!(EXPANDED_FUN_TIME_EXPANSION.contains("fn expanded() -> u8 {"));
+        assert!(EXPANDED_FUN_TIME_EXPANSION.contains("std::time::Instant::now()"));
+        assert!(EXPANDED_FUN_TIME_EXPANSION.contains("Done in"));
+    }
+
+    #[fun_time(when = "debug", expand_debug = true)]
+    fn expanded_when_debug() -> u8 {
+        42
+    }
+
+    #[test]
+    fn it_makes_expansion_available_when_debug() {
+        assert_eq!(42, expanded_when_debug());
+
+        // Also available in release builds, where the function is not timed
+        assert!(EXPANDED_WHEN_DEBUG_FUN_TIME_EXPANSION.contains("fn expanded_when_debug() -> u8 {"));
+    }
+
+    #[fun_time(accumulate)]
+    fn accumulated(sleep_millis: u64) {
+        std::thread::sleep(Duration::from_millis(sleep_millis));
+    }
+
+    fn totals_for(name: &str) -> crate::Totals {
+        crate::totals()
+            .into_iter()
+            .find_map(|(recorded_name, totals)| (recorded_name == name).then_some(totals))
+            .unwrap_or_else(|| panic!("no totals recorded for {name}"))
+    }
+
+    #[test]
+    fn it_accumulates_calls() {
+        accumulated(1);
+        accumulated(5);
+        accumulated(2);
+
+        let totals = totals_for("accumulated");
+        assert_eq!(3, totals.count);
+        assert!(totals.min >= Duration::from_millis(1));
+        assert!(totals.max >= Duration::from_millis(5));
+        assert!(totals.total >= Duration::from_millis(8));
+    }
+
+    #[fun_time(accumulate, warmup_calls = 5)]
+    fn warmed_up() {}
+
+    #[test]
+    fn it_skips_accumulating_warmup_calls() {
+        for _ in 0..15 {
+            warmed_up();
+        }
+
+        assert_eq!(10, totals_for("warmed_up").count);
+    }
+
+    #[test]
+    fn it_keeps_the_slowest_calls() {
+        for millis in [3, 9, 1, 7, 10, 2, 8, 4, 6, 5] {
+            crate::record("keeps_the_slowest_calls", Duration::from_millis(millis));
+        }
+
+        let totals = totals_for("keeps_the_slowest_calls");
+        assert_eq!(10, totals.count);
+        assert_eq!(Duration::from_millis(1), totals.min);
+        assert_eq!(Duration::from_millis(10), totals.max);
+        assert_eq!(Duration::from_micros(5500), totals.mean());
+        assert_eq!(
+            vec![10, 9, 8, 7, 6]
+                .into_iter()
+                .map(Duration::from_millis)
+                .collect::<Vec<_>>(),
+            totals.slowest()
+        );
+
+        assert!(crate::totals_report().contains(
+            "keeps_the_slowest_calls: 10 calls, total 55.00ms, mean 5.50ms, min 1.00ms, max 10.00ms, slowest: 10.00ms, 9.00ms, 8.00ms, 7.00ms, 6.00ms, rate "
+        ));
+    }
+
+    #[cfg(feature = "divan")]
+    mod feature_divan_tests {
+        use super::*;
+        use crate::divan::Samples;
+
+        #[test]
+        fn it_collects_given_back_durations() {
+            let samples = Samples::new();
+
+            let first = samples.record(borrow_or_own("first", false));
+            let second = samples.record(borrow_or_own("second", true));
+
+            assert_eq!("first", first);
+            assert_eq!("SECOND", second);
+            assert_eq!(2, samples.durations().len());
+        }
+    }
+
+    #[cfg(feature = "ffi")]
+    mod feature_ffi_tests {
+        use super::*;
+        use crate::ffi::{fun_time_begin, fun_time_end};
+        use std::ffi::CString;
+
+        #[test]
+        fn it_times_between_begin_and_end() {
+            let label = CString::new("work done in C").unwrap();
+
+            let handle = unsafe { fun_time_begin(label.as_ptr()) };
+            drop(label);
+            assert_ne!(0, handle);
+
+            std::thread::sleep(Duration::from_millis(10));
+
+            let elapsed_nanos = fun_time_end(handle);
+            assert!(Duration::from_nanos(elapsed_nanos) >= Duration::from_millis(10));
+
+            // A handle can only be ended once
+            assert_eq!(0, fun_time_end(handle));
+        }
+
+        #[test]
+        fn it_ignores_null_labels_and_unknown_handles() {
+            assert_eq!(0, unsafe { fun_time_begin(std::ptr::null()) });
+            assert_eq!(0, fun_time_end(0));
+        }
+    }
+
+    #[cfg(feature = "minitrace")]
+    mod feature_minitrace_tests {
+        use super::*;
+        use minitrace::collector::{Config, Reporter, SpanContext, SpanRecord};
+        use minitrace::Span;
+        use std::sync::{Arc, Mutex};
+
+        struct CollectingReporter(Arc<Mutex<Vec<SpanRecord>>>);
+
+        impl Reporter for CollectingReporter {
+            fn report(&mut self, spans: &[SpanRecord]) {
+                self.0.lock().unwrap().extend_from_slice(spans);
+            }
+        }
+
+        #[fun_time(message = "tracing {value}", reporting = "minitrace")]
+        fn traced(value: i32) -> i32 {
+            value * 2
+        }
+
+        struct NoDebug(i32);
+
+        #[fun_time(reporting = "minitrace", skip_properties = "handle")]
+        fn traced_without_debug(handle: NoDebug, _unused: NoDebug, value: i32) -> i32 {
+            handle.0 + value
+        }
+
+        #[test]
+        fn it_records_a_minitrace_span() {
+            // Works without a collector or a parent span
+            assert_eq!(2, traced(1));
+
+            let spans = Arc::new(Mutex::new(Vec::new()));
+            minitrace::set_reporter(CollectingReporter(spans.clone()), Config::default());
+
+            {
+                let root = Span::root("root", SpanContext::random());
+                let _guard = root.set_local_parent();
+
+                assert_eq!(4, traced(2));
+                assert_eq!(4, traced_without_debug(NoDebug(1), NoDebug(0), 3));
+            }
+            minitrace::flush();
+
+            let spans = spans.lock().unwrap();
+            let span = spans.iter().find(|span| span.name == "traced").unwrap();
+            assert_eq!(
+                vec![
+                    ("message".into(), "tracing 2".into()),
+                    ("value".into(), "2".into())
+                ],
+                span.properties
+            );
+
+            // Skipped and unused parameters do not need to implement `Debug`
+            let span = spans
+                .iter()
+                .find(|span| span.name == "traced_without_debug")
+                .unwrap();
+            assert_eq!(vec![("value".into(), "3".into())], span.properties);
+        }
+    }
+
+    #[cfg(feature = "prometheus-text")]
+    mod feature_prometheus_text_tests {
+        use super::*;
+
+        #[test]
+        fn it_renders_totals_as_prometheus_metrics() {
+            crate::record("rendered_as_metrics", Duration::from_millis(250));
+            crate::record("rendered_as_metrics", Duration::from_millis(500));
+            crate::record("rendered \"as\" metrics", Duration::from_secs(1));
+
+            let metrics = crate::prometheus_metrics();
+
+            // Every line is either a comment or a series with a value
+            let mut series = Vec::new();
+            for line in metrics.lines().filter(|line| !line.starts_with('#')) {
+                let (name, value) = line.rsplit_once(' ').unwrap();
+                let value: f64 = value.parse().unwrap();
+                series.push((name.to_string(), value));
+            }
+
+            assert!(metrics.contains("# TYPE fun_time_duration_seconds summary\n"));
+            assert!(series.contains(&(
+                "fun_time_duration_seconds_sum{fn=\"rendered_as_metrics\"}".to_string(),
+                0.75
+            )));
+            assert!(series.contains(&(
+                "fun_time_duration_seconds_count{fn=\"rendered_as_metrics\"}".to_string(),
+                2.0
+            )));
+            assert!(series.contains(&(
+                "fun_time_duration_seconds_sum{fn=\"rendered \\\"as\\\" metrics\"}".to_string(),
+                1.0
+            )));
+        }
+    }
+
+    #[cfg(feature = "puffin")]
+    mod feature_puffin_tests {
+        use super::*;
+
+        #[fun_time(message = "profiling {value}", reporting = "puffin")]
+        fn profiled(value: i32) -> i32 {
+            value * 2
+        }
+
+        #[test]
+        fn it_profiles_in_a_puffin_scope() {
+            // Works without the profiler turned on
+            assert_eq!(2, profiled(1));
+
+            puffin::set_scopes_on(true);
+            let frame_view = puffin::GlobalFrameView::default();
+
+            assert_eq!(4, profiled(2));
+            puffin::GlobalProfiler::lock().new_frame();
+
+            let frame = frame_view.lock().latest_frame().unwrap();
+            assert!(frame
+                .scope_delta
+                .iter()
+                .any(|scope| scope.scope_name.as_deref() == Some("profiled")));
+        }
+    }
+
     #[cfg(feature = "log")]
     mod feature_log_tests {
         use super::*;
         use simple_logger::SimpleLogger;
+        use std::cell::RefCell;
         use std::fmt::Formatter;
+        use std::sync::Once;
+
+        thread_local! {
+            static CAPTURED: RefCell<Vec<String>> = const { RefCell::new(Vec::new()) };
+        }
+
+        /// Logger that keeps the messages logged on the current thread, so tests can assert on
+        /// them, and then passes them on to the [`SimpleLogger`].
+        struct CapturingLogger(SimpleLogger);
+
+        impl log::Log for CapturingLogger {
+            fn enabled(&self, metadata: &log::Metadata) -> bool {
+                self.0.enabled(metadata)
+            }
+
+            fn log(&self, record: &log::Record) {
+                CAPTURED.with(|captured| captured.borrow_mut().push(record.args().to_string()));
+                self.0.log(record);
+            }
+
+            fn flush(&self) {
+                self.0.flush();
+            }
+        }
+
+        fn init_logger() {
+            static INIT: Once = Once::new();
+
+            INIT.call_once(|| {
+                log::set_boxed_logger(Box::new(CapturingLogger(SimpleLogger::new())))
+                    .expect("no other logger should be set");
+                log::set_max_level(log::LevelFilter::Trace);
+            });
+        }
+
+        /// Take the messages that were logged on the current thread so far.
+        fn take_captured() -> Vec<String> {
+            CAPTURED.with(|captured| captured.take())
+        }
 
         #[fun_time(when = "debug", message = "having fun with log", reporting = "log")]
         fn have_fun(_first: String, _second: String) {}
@@ -124,14 +892,14 @@ mod tests {
 
             assert_eq!(&"Hello, there!", borrowed_thing);
 
-            SimpleLogger::new().init().unwrap_or(());
+            init_logger();
 
             have_fun("Alice".to_string(), "Bob".to_string());
         }
 
         #[test]
         fn it_works_with_parameters() {
-            SimpleLogger::new().init().unwrap_or(());
+            init_logger();
 
             have_fun_with_parameters(
                 Parameter {
@@ -141,5 +909,176 @@ mod tests {
                 1234,
             );
         }
+
+        #[fun_time(message = "sleeping", adaptive_precision = true, reporting = "log")]
+        fn sleep_with_adaptive_precision(duration: Duration) {
+            std::thread::sleep(duration);
+        }
+
+        #[test]
+        fn it_reports_with_adaptive_precision() {
+            init_logger();
+            take_captured();
+
+            // Usually well below a millisecond, where the default would show 2 decimals instead
+            sleep_with_adaptive_precision(Duration::ZERO);
+
+            let captured = take_captured();
+            let (_, elapsed) = captured[1].split_once("Done in ").unwrap();
+            let (amount, expected_decimals) = [("ns", 0), ("µs", 3), ("ms", 2), ("s", 1)]
+                .into_iter()
+                .find_map(|(unit, decimals)| Some((elapsed.strip_suffix(unit)?, decimals)))
+                .unwrap();
+            let decimals = amount
+                .split_once('.')
+                .map_or(0, |(_, decimals)| decimals.len());
+            assert_eq!(expected_decimals, decimals, "{elapsed}");
+        }
+
+        #[fun_time(message = "initializing", mark_cold = true, reporting = "log")]
+        fn initialize() {}
+
+        #[test]
+        fn it_only_marks_the_first_call_as_cold() {
+            init_logger();
+            take_captured();
+
+            for _ in 0..3 {
+                initialize();
+            }
+
+            let done_messages: Vec<String> = take_captured()
+                .into_iter()
+                .filter(|message| message.starts_with("initializing: Done in "))
+                .collect();
+            assert_eq!(3, done_messages.len());
+            assert!(done_messages[0].ends_with(" [cold]"));
+            assert!(!done_messages[1].contains("[cold]"));
+            assert!(!done_messages[2].contains("[cold]"));
+        }
+
+        #[fun_time(message = "sleeping", relative = true, reporting = "log")]
+        fn sleep_relative(duration: Duration) {
+            std::thread::sleep(duration);
+        }
+
+        #[test]
+        fn it_reports_relative_to_the_mean() {
+            init_logger();
+            take_captured();
+
+            for _ in 0..3 {
+                sleep_relative(Duration::from_millis(5));
+            }
+            sleep_relative(Duration::from_millis(100));
+
+            let done_messages: Vec<String> = take_captured()
+                .into_iter()
+                .filter(|message| message.starts_with("sleeping: Done in "))
+                .collect();
+            assert!(done_messages[0].ends_with(" (n/a mean)"));
+
+            let (_, ratio) = done_messages[3].rsplit_once(" (").unwrap();
+            let ratio: f64 = ratio.trim_end_matches("x mean)").parse().unwrap();
+            assert!(ratio > 2.0, "unexpected ratio: {ratio}");
+        }
+
+        #[fun_time(message = "parent", hierarchical, reporting = "log")]
+        fn hierarchy_parent() {
+            hierarchy_child(7);
+        }
+
+        #[fun_time(message = "child {value}", hierarchical, reporting = "log")]
+        fn hierarchy_child(value: i32) {
+            let _ = value;
+        }
+
+        #[test]
+        fn it_prefixes_messages_with_the_hierarchy() {
+            init_logger();
+            take_captured();
+
+            hierarchy_parent();
+
+            let captured = take_captured();
+            assert_eq!("hierarchy_parent: parent", captured[0]);
+            assert_eq!("hierarchy_parent/hierarchy_child: child 7", captured[1]);
+            assert!(captured[2].starts_with("hierarchy_parent/hierarchy_child: child 7: Done in "));
+            assert!(captured[3].starts_with("hierarchy_parent: parent: Done in "));
+        }
+
+        #[fun_time(message = "correlating", epoch_us = true, reporting = "log")]
+        fn correlated() {}
+
+        #[test]
+        fn it_reports_epoch_us() {
+            use std::time::{SystemTime, UNIX_EPOCH};
+
+            init_logger();
+            take_captured();
+
+            let before = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
+            correlated();
+            let after = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
+
+            let captured = take_captured();
+            assert!(captured[1].starts_with("correlating: Done in "));
+            let (_, epoch_us) = captured[1].split_once(" epoch_us=").unwrap();
+            let epoch_us: u128 = epoch_us.parse().unwrap();
+            assert!(epoch_us >= before.as_micros() && epoch_us <= after.as_micros());
+        }
+
+        #[fun_time(message = "tuning", delta = true, reporting = "log")]
+        fn tuned(duration: Duration) {
+            std::thread::sleep(duration);
+        }
+
+        #[test]
+        fn it_reports_the_delta_to_the_previous_call() {
+            init_logger();
+            take_captured();
+
+            tuned(Duration::from_millis(5));
+            tuned(Duration::from_millis(50));
+            tuned(Duration::from_millis(5));
+
+            let done_messages: Vec<String> = take_captured()
+                .into_iter()
+                .filter(|message| message.starts_with("tuning: Done in "))
+                .collect();
+            assert!(!done_messages[0].contains("Δ"));
+            assert!(done_messages[1].contains(" (Δ +"));
+            assert!(done_messages[2].contains(" (Δ -"));
+        }
+
+        #[fun_time(
+            message = "looking up {key}",
+            early_return_message = "looking up {key} skipped",
+            reporting = "log"
+        )]
+        fn look_up(key: &str) -> Option<usize> {
+            if key.is_empty() {
+                fun_time::early!();
+                return None;
+            }
+
+            Some(key.len())
+        }
+
+        #[test]
+        fn it_uses_early_return_message_after_early_return() {
+            init_logger();
+            take_captured();
+
+            assert_eq!(Some(5), look_up("hello"));
+            assert_eq!(None, look_up(""));
+
+            let captured = take_captured();
+            assert_eq!(4, captured.len());
+            assert_eq!("looking up hello", captured[0]);
+            assert!(captured[1].starts_with("looking up hello: Done in "));
+            assert_eq!("looking up ", captured[2]);
+            assert!(captured[3].starts_with("looking up  skipped: Done in "));
+        }
     }
 }
@@ -0,0 +1,48 @@
+//! Rendering of the accumulated execution times in the Prometheus text exposition format.
+
+use std::fmt::Write;
+
+/// Render the accumulated execution times, see [`crate::totals`], in the Prometheus text
+/// exposition format, so they can be served at a `/metrics` endpoint.
+///
+/// Every function is a series of the `fun_time_duration_seconds` summary, with the name of the
+/// function in the `fn` label:
+///
+/// ```text
+/// # HELP fun_time_duration_seconds Execution time of functions timed by fun_time.
+/// # TYPE fun_time_duration_seconds summary
+/// fun_time_duration_seconds_sum{fn="load_config"} 0.012345
+/// fun_time_duration_seconds_count{fn="load_config"} 3
+/// ```
+pub fn prometheus_metrics() -> String {
+    let mut metrics = String::from(
+        "# HELP fun_time_duration_seconds Execution time of functions timed by fun_time.\n\
+         # TYPE fun_time_duration_seconds summary\n",
+    );
+
+    for (name, totals) in crate::totals() {
+        let name = escape_label_value(&name);
+
+        // Writing to a `String` can not fail
+        let _ = writeln!(
+            metrics,
+            "fun_time_duration_seconds_sum{{fn=\"{name}\"}} {}",
+            totals.total.as_secs_f64()
+        );
+        let _ = writeln!(
+            metrics,
+            "fun_time_duration_seconds_count{{fn=\"{name}\"}} {}",
+            totals.count
+        );
+    }
+
+    metrics
+}
+
+/// Escape a label value as required by the exposition format.
+fn escape_label_value(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
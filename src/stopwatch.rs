@@ -0,0 +1,73 @@
+//! Manual timing of named intervals, for code that can not be put in a timed function.
+
+use std::collections::HashMap;
+use std::sync::{Mutex, MutexGuard, OnceLock};
+use std::time::{Duration, Instant};
+
+/// Global stopwatch that accumulates named intervals in the same registry as the `accumulate`
+/// attribute, see [`crate::report_totals`].
+///
+/// A stopwatch is identified by its name, so it can be started and stopped in different places,
+/// and even on different threads.
+///
+/// ```
+/// use fun_time::Stopwatch;
+///
+/// Stopwatch::start("loading");
+/// // Read the files...
+/// Stopwatch::lap("loading");
+/// // Parse the files...
+/// Stopwatch::stop("loading");
+///
+/// let _ = fun_time::report_totals();
+/// ```
+pub struct Stopwatch;
+
+fn running() -> MutexGuard<'static, HashMap<String, Instant>> {
+    static RUNNING: OnceLock<Mutex<HashMap<String, Instant>>> = OnceLock::new();
+
+    RUNNING
+        .get_or_init(Default::default)
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+impl Stopwatch {
+    /// Start the stopwatch with the given name.
+    ///
+    /// Starting a stopwatch that is already running restarts it, without recording the interval
+    /// since it was started.
+    pub fn start(name: &str) {
+        running().insert(name.to_string(), Instant::now());
+    }
+
+    /// Record the interval since the stopwatch with the given name was started, or since its
+    /// previous lap, and keep it running.
+    ///
+    /// Returns `None` without recording anything if the stopwatch is not running.
+    pub fn lap(name: &str) -> Option<Duration> {
+        let elapsed = {
+            let mut running = running();
+            let started = running.get_mut(name)?;
+            let now = Instant::now();
+
+            now.duration_since(std::mem::replace(started, now))
+        };
+
+        crate::record(name, elapsed);
+
+        Some(elapsed)
+    }
+
+    /// Record the interval since the stopwatch with the given name was started, or since its
+    /// previous lap, and stop it.
+    ///
+    /// Returns `None` without recording anything if the stopwatch is not running.
+    pub fn stop(name: &str) -> Option<Duration> {
+        let elapsed = running().remove(name)?.elapsed();
+
+        crate::record(name, elapsed);
+
+        Some(elapsed)
+    }
+}
@@ -0,0 +1,243 @@
+//! Accumulation of the execution times of functions using the `accumulate` attribute.
+
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BinaryHeap};
+use std::fmt::{Display, Formatter, Write};
+use std::sync::{Mutex, MutexGuard, OnceLock};
+use std::time::{Duration, Instant};
+
+/// The number of slowest individual calls that are kept for every function.
+pub const SLOWEST_CALLS_KEPT: usize = 5;
+
+/// The accumulated execution times of a single function.
+#[derive(Clone, Debug, Default)]
+pub struct Totals {
+    /// The number of recorded calls.
+    pub count: u64,
+    /// The sum of the execution times of all recorded calls.
+    pub total: Duration,
+    /// The execution time of the fastest recorded call.
+    pub min: Duration,
+    /// The execution time of the slowest recorded call.
+    pub max: Duration,
+    /// The maximum mean execution time, when a budget was set.
+    pub budget: Option<Duration>,
+    /// When the first recorded call was done.
+    pub first_call: Option<Instant>,
+    /// When the last recorded call was done.
+    pub last_call: Option<Instant>,
+    /// Min-heap of the slowest calls, bounded to [`SLOWEST_CALLS_KEPT`] items.
+    slowest: BinaryHeap<Reverse<Duration>>,
+}
+
+impl Totals {
+    fn record(&mut self, elapsed: Duration) {
+        self.min = if self.count == 0 {
+            elapsed
+        } else {
+            self.min.min(elapsed)
+        };
+        self.max = self.max.max(elapsed);
+        self.count += 1;
+        self.total += elapsed;
+
+        let now = Instant::now();
+        self.first_call.get_or_insert(now);
+        self.last_call = Some(now);
+
+        // Only keep the slowest calls, the root of the heap is the fastest one we kept so far
+        if self.slowest.len() < SLOWEST_CALLS_KEPT {
+            self.slowest.push(Reverse(elapsed));
+        } else if let Some(mut fastest_kept) = self.slowest.peek_mut() {
+            if elapsed > fastest_kept.0 {
+                *fastest_kept = Reverse(elapsed);
+            }
+        }
+    }
+
+    /// The mean execution time of the recorded calls.
+    pub fn mean(&self) -> Duration {
+        match u32::try_from(self.count) {
+            Ok(0) => Duration::ZERO,
+            Ok(count) => self.total / count,
+            Err(_) => Duration::from_secs_f64(self.total.as_secs_f64() / self.count as f64),
+        }
+    }
+
+    /// The observed number of calls per second, between the first and the last recorded call.
+    ///
+    /// Returns `None` when fewer than 2 calls were recorded, because there is no window yet.
+    pub fn rate(&self) -> Option<f64> {
+        let window = self.last_call?.duration_since(self.first_call?);
+
+        if self.count < 2 || window.is_zero() {
+            return None;
+        }
+
+        // The window starts at the first call, so it only spans the calls after it
+        Some((self.count - 1) as f64 / window.as_secs_f64())
+    }
+
+    /// Whether the mean execution time exceeds the budget, if one was set.
+    pub fn exceeds_budget(&self) -> bool {
+        self.budget.is_some_and(|budget| self.mean() > budget)
+    }
+
+    /// The execution times of the slowest recorded calls, slowest first.
+    pub fn slowest(&self) -> Vec<Duration> {
+        let mut slowest: Vec<Duration> = self.slowest.iter().map(|kept| kept.0).collect();
+        slowest.sort_unstable_by(|a, b| b.cmp(a));
+        slowest
+    }
+}
+
+fn registry() -> MutexGuard<'static, BTreeMap<String, Totals>> {
+    static REGISTRY: OnceLock<Mutex<BTreeMap<String, Totals>>> = OnceLock::new();
+
+    REGISTRY
+        .get_or_init(Default::default)
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// A function of which the mean execution time exceeds its budget.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BudgetViolation {
+    /// The name the execution times were recorded under.
+    pub name: String,
+    /// The mean execution time of the recorded calls.
+    pub mean: Duration,
+    /// The budget that was exceeded.
+    pub budget: Duration,
+}
+
+impl Display for BudgetViolation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}: mean {:.2?} exceeds budget of {:.2?}",
+            self.name, self.mean, self.budget
+        )
+    }
+}
+
+/// Get the totals for the given name, without allocating the name if they already exist.
+fn entry<'a>(registry: &'a mut BTreeMap<String, Totals>, name: &str) -> &'a mut Totals {
+    if !registry.contains_key(name) {
+        registry.insert(name.to_string(), Totals::default());
+    }
+
+    registry.get_mut(name).expect("totals were just inserted")
+}
+
+/// Record the execution time of a single call under the given name.
+pub fn record(name: &str, elapsed: Duration) {
+    entry(&mut registry(), name).record(elapsed);
+
+    crate::csv_log::append(name, elapsed);
+}
+
+/// Record the execution time of a single call under the given name, and set the budget that the
+/// mean execution time should stay within, see [`report_totals`].
+pub fn record_with_budget(name: &str, elapsed: Duration, budget: Duration) {
+    let mut registry = registry();
+    let totals = entry(&mut registry, name);
+
+    totals.budget = Some(budget);
+    totals.record(elapsed);
+    drop(registry);
+
+    crate::csv_log::append(name, elapsed);
+}
+
+/// Get a snapshot of the accumulated execution times, sorted by name.
+pub fn totals() -> Vec<(String, Totals)> {
+    registry()
+        .iter()
+        .map(|(name, totals)| (name.clone(), totals.clone()))
+        .collect()
+}
+
+/// Forget all accumulated execution times.
+pub fn reset_totals() {
+    registry().clear();
+}
+
+/// Render the accumulated execution times as a human readable summary.
+pub fn totals_report() -> String {
+    let mut report = String::new();
+
+    for (name, totals) in totals() {
+        let slowest = totals
+            .slowest()
+            .iter()
+            .map(|elapsed| format!("{elapsed:.2?}"))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let rate = totals
+            .rate()
+            .map_or_else(|| "n/a".to_string(), |rate| format!("{rate:.2}/s"));
+
+        // Writing to a `String` can not fail
+        let _ = writeln!(
+            report,
+            "{name}: {count} calls, total {total:.2?}, mean {mean:.2?}, min {min:.2?}, max {max:.2?}, slowest: {slowest}, rate {rate}",
+            count = totals.count,
+            total = totals.total,
+            mean = totals.mean(),
+            min = totals.min,
+            max = totals.max,
+        );
+    }
+
+    report
+}
+
+/// Get the functions of which the mean execution time exceeds their budget, sorted by name.
+pub fn budget_violations() -> Vec<BudgetViolation> {
+    registry()
+        .iter()
+        .filter(|(_, totals)| totals.exceeds_budget())
+        .map(|(name, totals)| BudgetViolation {
+            name: name.clone(),
+            mean: totals.mean(),
+            budget: totals.budget.unwrap_or_default(),
+        })
+        .collect()
+}
+
+/// Report the accumulated execution times, using the `log` crate when the `log` feature is
+/// enabled, or `println!` otherwise.
+///
+/// Returns the budget violations, which are also reported, as an error if the mean execution
+/// time of any function exceeds its budget. This can be used as a gate in CI:
+///
+/// ```no_run
+/// // Run the code you want to check...
+///
+/// if let Err(violations) = fun_time::report_totals() {
+///     panic!("{} functions are over budget", violations.len());
+/// }
+/// ```
+pub fn report_totals() -> Result<(), Vec<BudgetViolation>> {
+    for line in totals_report().lines() {
+        #[cfg(not(feature = "log"))]
+        println!("{line}");
+        #[cfg(feature = "log")]
+        log::info!("{line}");
+    }
+
+    let violations = budget_violations();
+    if violations.is_empty() {
+        return Ok(());
+    }
+
+    for violation in &violations {
+        #[cfg(not(feature = "log"))]
+        println!("{violation}");
+        #[cfg(feature = "log")]
+        log::warn!("{violation}");
+    }
+
+    Err(violations)
+}